@@ -0,0 +1,278 @@
+//! Module containing an abstract stack-height tracking pass.
+
+use crate::{
+    cfg::{Cfg, Edge},
+    instruction::Instruction,
+};
+use std::collections::HashMap;
+
+/// The stack height annotated before a single instruction, along with
+/// whether executing that instruction would underflow the stack.
+pub struct StackHeight {
+    /// The byte offset of the instruction.
+    pub offset: usize,
+    /// The symbolic stack height immediately before the instruction.
+    pub height: i64,
+    /// Whether this instruction pops more items than are known to be on the
+    /// stack at this point.
+    pub underflow: bool,
+}
+
+/// The result of the stack-effect analysis pass.
+pub struct StackAnalysis {
+    /// The stack height before each instruction, in program order.
+    pub heights: Vec<StackHeight>,
+    /// Diagnostics raised while tracking stack heights, e.g. a block whose
+    /// exit height disagrees with what a successor expects.
+    pub diagnostics: Vec<String>,
+}
+
+/// Walks the instruction stream maintaining a symbolic stack depth starting
+/// at 0, mirroring the pop/push arity of each instruction.
+///
+/// When a [`Cfg`] is available, the height is reset/merged at block
+/// boundaries using the blocks' resolved successor edges; otherwise the
+/// instructions are walked as a single sequential block.
+pub fn analyze(instructions: &[(usize, Instruction)], cfg: Option<&Cfg>) -> StackAnalysis {
+    match cfg {
+        Some(cfg) => analyze_with_cfg(cfg),
+        None => {
+            let mut heights = Vec::with_capacity(instructions.len());
+            let diagnostics = Vec::new();
+            walk_block(instructions, 0, &mut heights);
+            StackAnalysis {
+                heights,
+                diagnostics,
+            }
+        }
+    }
+}
+
+/// Walks a single basic block starting from `entry_height`, appending a
+/// [`StackHeight`] per instruction, and returns the height on exit.
+fn walk_block(
+    instructions: &[(usize, Instruction)],
+    entry_height: i64,
+    heights: &mut Vec<StackHeight>,
+) -> i64 {
+    let mut height = entry_height;
+    for (offset, instruction) in instructions {
+        let (pops, pushes) = instruction.stack_effect();
+        let underflow = height < i64::from(pops);
+        heights.push(StackHeight {
+            offset: *offset,
+            height,
+            underflow,
+        });
+        height += i64::from(pushes) - i64::from(pops);
+    }
+    height
+}
+
+/// The number of passes to settle [`expected_entry`] to a fixed point before
+/// giving up and reporting whatever mismatches remain. Bounded so that a
+/// genuinely height-growing loop (stack-inconsistent code) can't spin
+/// forever; legitimate loops settle in a handful of passes.
+const MAX_SETTLE_PASSES: usize = 64;
+
+fn analyze_with_cfg(cfg: &Cfg) -> StackAnalysis {
+    let mut expected_entry = HashMap::<usize, i64>::new();
+
+    // A block's true entry height can come from a predecessor that appears
+    // later in program order than the block itself -- a back edge, which is
+    // the defining shape of a loop. A single forward pass over `cfg.blocks()`
+    // only ever sees predecessors that precede their successor textually, so
+    // settle `expected_entry` to a fixed point first.
+    let mut scratch = Vec::new();
+    for _ in 0..MAX_SETTLE_PASSES {
+        let mut changed = false;
+        for block in cfg.blocks() {
+            let entry_height = *expected_entry.get(&block.start).unwrap_or(&0);
+            scratch.clear();
+            let exit_height = walk_block(&block.instructions, entry_height, &mut scratch);
+
+            for target in successors(block) {
+                if expected_entry.get(&target) != Some(&exit_height) {
+                    expected_entry.insert(target, exit_height);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut heights = Vec::new();
+    let mut diagnostics = Vec::new();
+    for block in cfg.blocks() {
+        let entry_height = *expected_entry.get(&block.start).unwrap_or(&0);
+        let exit_height = walk_block(&block.instructions, entry_height, &mut heights);
+
+        for target in successors(block) {
+            if let Some(existing) = expected_entry.get(&target) {
+                if *existing != exit_height {
+                    diagnostics.push(format!(
+                        "block :{:x} leaves height {} but successor :{:x} expects {}",
+                        block.start, exit_height, target, existing
+                    ));
+                }
+            }
+        }
+    }
+
+    StackAnalysis {
+        heights,
+        diagnostics,
+    }
+}
+
+/// Returns the resolved successor offsets of a block's edges, skipping
+/// unresolved (dynamic) jumps.
+fn successors(block: &crate::cfg::Block) -> impl Iterator<Item = usize> + '_ {
+    block.edges.iter().filter_map(|edge| match edge {
+        Edge::Jump(target) | Edge::Fallthrough(target) => Some(*target),
+        Edge::Unresolved => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethnum::U256;
+
+    #[test]
+    fn tracks_height_across_push_and_binop() {
+        let instructions = vec![
+            (0, Instruction::Push(1, U256::from(1u64))),
+            (2, Instruction::Push(1, U256::from(2u64))),
+            (4, Instruction::Add),
+        ];
+        let analysis = analyze(&instructions, None);
+
+        let heights = analysis
+            .heights
+            .iter()
+            .map(|h| h.height)
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![0, 1, 2]);
+        assert!(analysis.heights.iter().all(|h| !h.underflow));
+    }
+
+    #[test]
+    fn flags_underflow_on_pop_with_empty_stack() {
+        let instructions = vec![(0, Instruction::Pop)];
+        let analysis = analyze(&instructions, None);
+
+        assert!(analysis.heights[0].underflow);
+    }
+
+    #[test]
+    fn dup_and_swap_use_index_as_precondition_height() {
+        // dup3 requires 3 items already on the stack to be well-formed.
+        let instructions = vec![
+            (0, Instruction::Push(1, U256::from(1u64))),
+            (2, Instruction::Push(1, U256::from(2u64))),
+            (4, Instruction::Dup(3)),
+        ];
+        let analysis = analyze(&instructions, None);
+
+        // Before the dup, only 2 items are on the stack, so it underflows.
+        assert!(analysis.heights[2].underflow);
+
+        let instructions = vec![
+            (0, Instruction::Push(1, U256::from(1u64))),
+            (2, Instruction::Push(1, U256::from(2u64))),
+            (4, Instruction::Push(1, U256::from(3u64))),
+            (6, Instruction::Swap(2)),
+        ];
+        let analysis = analyze(&instructions, None);
+        assert!(!analysis.heights[3].underflow);
+    }
+
+    #[test]
+    fn cross_block_merge_propagates_exit_height_to_successor() {
+        // push1 0x2a; push1 0x05; jump; jumpdest; pop
+        let instructions = vec![
+            (0, Instruction::Push(1, U256::from(0x2au64))),
+            (2, Instruction::Push(1, U256::from(5u64))),
+            (4, Instruction::Jump),
+            (5, Instruction::JumpDest(5)),
+            (6, Instruction::Pop),
+        ];
+        let cfg = Cfg::build(instructions.clone());
+        let analysis = analyze(&instructions, Some(&cfg));
+
+        let pop_height = analysis
+            .heights
+            .iter()
+            .find(|h| h.offset == 6)
+            .unwrap()
+            .height;
+        assert_eq!(pop_height, 1);
+        assert!(analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn diagnoses_inconsistent_heights_from_multiple_predecessors() {
+        // Block A jumps to :9 leaving one item on the stack; block B falls
+        // through into the same :9 leaving two, so the merge disagrees.
+        let instructions = vec![
+            (0, Instruction::Push(1, U256::from(1u64))),
+            (2, Instruction::Push(1, U256::from(9u64))),
+            (4, Instruction::Jump),
+            (5, Instruction::Push(1, U256::from(0xbbu64))),
+            (7, Instruction::Push(1, U256::from(0xccu64))),
+            (9, Instruction::JumpDest(9)),
+            (10, Instruction::Stop),
+        ];
+        let cfg = Cfg::build(instructions.clone());
+        let analysis = analyze(&instructions, Some(&cfg));
+
+        assert!(!analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn diagnoses_inconsistent_back_edge_loop() {
+        // jumpdest:0; push1 9; jump (block A) -- dead stops -- jumpdest:9;
+        // push1 1; push1 0; jump (block B, a back edge to block A). Each
+        // trip around the loop leaves one extra item on the stack, so the
+        // entry height can never settle.
+        let instructions = vec![
+            (0, Instruction::JumpDest(0)),
+            (1, Instruction::Push(1, U256::from(9u64))),
+            (3, Instruction::Jump),
+            (4, Instruction::Stop),
+            (5, Instruction::Stop),
+            (6, Instruction::Stop),
+            (7, Instruction::Stop),
+            (8, Instruction::Stop),
+            (9, Instruction::JumpDest(9)),
+            (10, Instruction::Push(1, U256::from(1u64))),
+            (12, Instruction::Push(1, U256::from(0u64))),
+            (14, Instruction::Jump),
+        ];
+        let cfg = Cfg::build(instructions.clone());
+        let analysis = analyze(&instructions, Some(&cfg));
+
+        assert!(!analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn stable_self_loop_via_back_edge_is_not_flagged() {
+        // jumpdest:0; push1 1; pop; push1 0; jump -- a loop whose body nets
+        // zero height change per iteration, so the back edge settles to a
+        // consistent entry height and should not be diagnosed.
+        let instructions = vec![
+            (0, Instruction::JumpDest(0)),
+            (1, Instruction::Push(1, U256::from(1u64))),
+            (3, Instruction::Pop),
+            (4, Instruction::Push(1, U256::from(0u64))),
+            (6, Instruction::Jump),
+        ];
+        let cfg = Cfg::build(instructions.clone());
+        let analysis = analyze(&instructions, Some(&cfg));
+
+        assert!(analysis.diagnostics.is_empty());
+    }
+}
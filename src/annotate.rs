@@ -0,0 +1,219 @@
+//! Module recognizing common call and dispatcher idioms using the lightweight
+//! constant propagation that falls out of tracking the symbolic stack.
+
+use crate::{cfg::Cfg, instruction::Instruction};
+use ethnum::U256;
+use std::collections::{HashMap, HashSet};
+
+/// Returns the name of the well-known precompiled contract at `address`, if
+/// any.
+fn precompile_name(address: u8) -> Option<&'static str> {
+    match address {
+        0x01 => Some("ecrecover"),
+        0x02 => Some("sha256"),
+        0x03 => Some("ripemd160"),
+        0x04 => Some("identity"),
+        0x05 => Some("modexp"),
+        0x06 => Some("ecadd"),
+        0x07 => Some("ecmul"),
+        0x08 => Some("ecpairing"),
+        0x09 => Some("blake2f"),
+        0x0a => Some("point_evaluation"),
+        _ => None,
+    }
+}
+
+/// Produces inline comments, keyed by instruction offset, annotating calls
+/// into known precompiles and Solidity-style 4-byte selector dispatch.
+pub fn annotate(cfg: &Cfg) -> HashMap<usize, String> {
+    let mut comments = HashMap::new();
+    for block in cfg.blocks() {
+        annotate_block(&block.instructions, cfg.jump_dests(), &mut comments);
+    }
+    comments
+}
+
+/// A stack slot that is either a known constant (tracked size and value, as
+/// produced by a `PUSH` or copied by a `DUP`) or unknown.
+type ConstStack = Vec<Option<(u8, U256)>>;
+
+fn annotate_block(
+    instructions: &[(usize, Instruction)],
+    jump_dests: &HashSet<usize>,
+    comments: &mut HashMap<usize, String>,
+) {
+    let mut stack = ConstStack::new();
+    for (i, (offset, instruction)) in instructions.iter().enumerate() {
+        use Instruction::*;
+        match instruction {
+            Call | CallCode | DelegateCall | StaticCall => {
+                // The callee address is always the second argument from the
+                // top: `gas, address, ...`.
+                if let Some(Some((_, address))) = stack.iter().rev().nth(1).copied() {
+                    if address <= U256::from(u8::MAX as u64) {
+                        if let Some(name) = precompile_name(address.as_u8()) {
+                            comments.insert(
+                                *offset,
+                                format!("{} (precompile 0x{:02x})", name, address.as_u8()),
+                            );
+                        }
+                    }
+                }
+            }
+            Eq | Lt | Gt => {
+                if let Some((jumpi_offset, dest, selector)) =
+                    dispatch_target(instructions, i, jump_dests, &stack)
+                {
+                    comments.insert(
+                        jumpi_offset,
+                        format!("selector 0x{:08x} -> :{:x}", selector, dest),
+                    );
+                }
+            }
+            _ => {}
+        }
+
+        apply(&mut stack, instruction);
+    }
+}
+
+/// If the comparison at index `i` (an `EQ`/`LT`/`GT`) is immediately followed
+/// by `PUSH <dest>; JUMPI` where `dest` is a valid jump destination, and one
+/// of the comparison's two operands is a known 4-byte constant (a selector),
+/// returns the `JUMPI`'s offset, the jump destination, and the selector.
+fn dispatch_target(
+    instructions: &[(usize, Instruction)],
+    i: usize,
+    jump_dests: &HashSet<usize>,
+    stack: &ConstStack,
+) -> Option<(usize, usize, U256)> {
+    let (jumpi_offset, dest) = match (instructions.get(i + 1), instructions.get(i + 2)) {
+        (Some((_, Instruction::Push(_, dest))), Some((jumpi_offset, Instruction::JumpI))) => {
+            (*jumpi_offset, *dest)
+        }
+        _ => return None,
+    };
+    if dest > U256::from(usize::MAX as u64) {
+        return None;
+    }
+    let dest = dest.as_usize();
+    if !jump_dests.contains(&dest) {
+        return None;
+    }
+
+    let selector = stack.iter().rev().take(2).find_map(|slot| match slot {
+        Some((4, value)) => Some(*value),
+        _ => None,
+    })?;
+    Some((jumpi_offset, dest, selector))
+}
+
+/// Updates the symbolic constant stack to reflect executing `instruction`.
+fn apply(stack: &mut ConstStack, instruction: &Instruction) {
+    use Instruction::*;
+    match instruction {
+        Push(size, value) => stack.push(Some((*size, *value))),
+        Dup(n) => {
+            let n = *n as usize;
+            let value = stack
+                .len()
+                .checked_sub(n)
+                .and_then(|i| stack.get(i))
+                .copied()
+                .flatten();
+            stack.push(value);
+        }
+        Swap(n) => {
+            let n = *n as usize;
+            let len = stack.len();
+            if len > n {
+                stack.swap(len - 1, len - 1 - n);
+            }
+        }
+        _ => {
+            let (pops, pushes) = instruction.stack_effect();
+            for _ in 0..pops {
+                stack.pop();
+            }
+            for _ in 0..pushes {
+                stack.push(None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfg::Cfg;
+
+    #[test]
+    fn annotates_call_to_known_precompile() {
+        // push1 <argsOffset args...>; push1 0x01 (ecrecover); push2 <gas>; call
+        let instructions = vec![
+            (0, Instruction::Push(1, U256::from(0u64))),
+            (2, Instruction::Push(1, U256::from(0u64))),
+            (4, Instruction::Push(1, U256::from(0u64))),
+            (6, Instruction::Push(1, U256::from(0u64))),
+            (8, Instruction::Push(1, U256::from(1u64))),
+            (10, Instruction::Push(2, U256::from(0xffffu64))),
+            (13, Instruction::Call),
+        ];
+        let cfg = Cfg::build(instructions);
+        let comments = annotate(&cfg);
+
+        assert_eq!(comments[&13], "ecrecover (precompile 0x01)");
+    }
+
+    #[test]
+    fn does_not_annotate_call_to_unknown_address() {
+        let instructions = vec![
+            (0, Instruction::Push(1, U256::from(0u64))),
+            (2, Instruction::Push(1, U256::from(0u64))),
+            (4, Instruction::Push(1, U256::from(0u64))),
+            (6, Instruction::Push(1, U256::from(0u64))),
+            (8, Instruction::Push(20, U256::from(0x1234u64))),
+            (29, Instruction::Push(2, U256::from(0xffffu64))),
+            (32, Instruction::Call),
+        ];
+        let cfg = Cfg::build(instructions);
+        let comments = annotate(&cfg);
+
+        assert!(comments.is_empty());
+    }
+
+    #[test]
+    fn annotates_four_byte_selector_dispatch() {
+        // calldataload; push4 <selector> (on top of the calldata value); eq;
+        // push1 <dest>; jumpi; jumpdest
+        let instructions = vec![
+            (0, Instruction::CallDataLoad),
+            (1, Instruction::Push(4, U256::from(0x12345678u64))),
+            (6, Instruction::Eq),
+            (7, Instruction::Push(1, U256::from(10u64))),
+            (9, Instruction::JumpI),
+            (10, Instruction::JumpDest(10)),
+            (11, Instruction::Stop),
+        ];
+        let cfg = Cfg::build(instructions);
+        let comments = annotate(&cfg);
+
+        assert_eq!(comments[&9], "selector 0x12345678 -> :a");
+    }
+
+    #[test]
+    fn no_dispatch_comment_when_jumpi_target_is_not_a_jumpdest() {
+        let instructions = vec![
+            (0, Instruction::CallDataLoad),
+            (1, Instruction::Push(4, U256::from(0x12345678u64))),
+            (6, Instruction::Eq),
+            (7, Instruction::Push(1, U256::from(99u64))),
+            (9, Instruction::JumpI),
+            (10, Instruction::Stop),
+        ];
+        let cfg = Cfg::build(instructions);
+        let comments = annotate(&cfg);
+
+        assert!(comments.is_empty());
+    }
+}
@@ -0,0 +1,271 @@
+//! Module containing a control-flow graph pass over a decoded instruction
+//! stream.
+
+use crate::instruction::Instruction;
+use ethnum::U256;
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+};
+
+/// An edge leaving a basic block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// An unconditional or taken-branch jump to a known `JUMPDEST` offset.
+    Jump(usize),
+    /// Sequential fall-through into the next block, either because a
+    /// conditional jump was not taken or because control simply flows into
+    /// the following instruction.
+    Fallthrough(usize),
+    /// A jump whose target could not be resolved statically.
+    Unresolved,
+}
+
+/// A maximal run of instructions with a single entry and a single exit.
+pub struct Block {
+    /// The byte offset of the first instruction in the block.
+    pub start: usize,
+    /// The instructions making up the block, keyed by byte offset.
+    pub instructions: Vec<(usize, Instruction)>,
+    /// The edges leaving this block.
+    pub edges: Vec<Edge>,
+}
+
+/// A control-flow graph over a decoded instruction stream.
+pub struct Cfg {
+    blocks: Vec<Block>,
+    jump_dests: HashSet<usize>,
+}
+
+impl Cfg {
+    /// Builds a control-flow graph from a buffered instruction stream,
+    /// resolving static jump targets using the standard peephole heuristic:
+    /// a `JUMP`/`JUMPI` whose immediately preceding instruction is a `PUSH`
+    /// of a valid `JUMPDEST` offset is treated as a concrete edge.
+    pub fn build(instructions: Vec<(usize, Instruction)>) -> Self {
+        let jump_dests = instructions
+            .iter()
+            .filter_map(|(offset, instruction)| match instruction {
+                Instruction::JumpDest(_) => Some(*offset),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+
+        let mut blocks = Vec::<Block>::new();
+        let mut current = Vec::<(usize, Instruction)>::new();
+        let mut ends_block = false;
+        for (offset, instruction) in instructions {
+            let starts_block = matches!(instruction, Instruction::JumpDest(_));
+            if (ends_block || starts_block) && !current.is_empty() {
+                blocks.push(Block {
+                    start: current[0].0,
+                    instructions: std::mem::take(&mut current),
+                    edges: Vec::new(),
+                });
+            }
+
+            ends_block = matches!(
+                instruction,
+                Instruction::Jump
+                    | Instruction::JumpI
+                    | Instruction::Stop
+                    | Instruction::Return
+                    | Instruction::Revert
+                    | Instruction::Invalid
+                    | Instruction::SelfDestruct
+            );
+            current.push((offset, instruction));
+        }
+        if !current.is_empty() {
+            blocks.push(Block {
+                start: current[0].0,
+                instructions: current,
+                edges: Vec::new(),
+            });
+        }
+
+        let block_starts = blocks.iter().map(|block| block.start).collect::<Vec<_>>();
+        let edges_per_block = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| {
+                let next_start = block_starts.get(i + 1).copied();
+                match block.instructions.last() {
+                    Some((_, Instruction::Jump)) => {
+                        vec![resolve_jump(block, &jump_dests)]
+                    }
+                    Some((_, Instruction::JumpI)) => {
+                        let mut edges = vec![resolve_jump(block, &jump_dests)];
+                        if let Some(next_start) = next_start {
+                            edges.push(Edge::Fallthrough(next_start));
+                        }
+                        edges
+                    }
+                    Some((
+                        _,
+                        Instruction::Stop
+                        | Instruction::Return
+                        | Instruction::Revert
+                        | Instruction::Invalid
+                        | Instruction::SelfDestruct,
+                    )) => Vec::new(),
+                    _ => next_start.into_iter().map(Edge::Fallthrough).collect(),
+                }
+            })
+            .collect::<Vec<_>>();
+        for (block, edges) in blocks.iter_mut().zip(edges_per_block) {
+            block.edges = edges;
+        }
+
+        Self {
+            blocks,
+            jump_dests,
+        }
+    }
+
+    /// Returns the basic blocks making up this control-flow graph, in
+    /// program order.
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Returns the set of valid `JUMPDEST` offsets used to resolve edges.
+    pub fn jump_dests(&self) -> &HashSet<usize> {
+        &self.jump_dests
+    }
+
+    /// Renders this control-flow graph as Graphviz DOT.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cfg {\n");
+        for block in &self.blocks {
+            let label = block
+                .instructions
+                .iter()
+                .map(|(offset, instruction)| format!("{:x}: {}", offset, instruction))
+                .collect::<Vec<_>>()
+                .join("\\l");
+            dot.push_str(&format!(
+                "  \"{:x}\" [shape=box, label=\"{}\\l\"];\n",
+                block.start, label,
+            ));
+            for edge in &block.edges {
+                match edge {
+                    Edge::Jump(target) => {
+                        dot.push_str(&format!("  \"{:x}\" -> \"{:x}\";\n", block.start, target))
+                    }
+                    Edge::Fallthrough(target) => dot.push_str(&format!(
+                        "  \"{:x}\" -> \"{:x}\" [style=dashed];\n",
+                        block.start, target
+                    )),
+                    Edge::Unresolved => dot.push_str(&format!(
+                        "  \"{:x}\" -> \"unresolved_{:x}\" [style=dotted];\n",
+                        block.start, block.start
+                    )),
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Resolves the jump target of a block ending in `JUMP`/`JUMPI` using the
+/// preceding-`PUSH` peephole heuristic.
+fn resolve_jump(block: &Block, jump_dests: &HashSet<usize>) -> Edge {
+    let target = match block.instructions.iter().rev().nth(1) {
+        Some((_, Instruction::Push(_, value))) if *value <= U256::from(usize::MAX as u64) => {
+            Some(value.as_usize())
+        }
+        _ => None,
+    };
+    match target {
+        Some(target) if jump_dests.contains(&target) => Edge::Jump(target),
+        _ => Edge::Unresolved,
+    }
+}
+
+impl Display for Cfg {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for block in &self.blocks {
+            writeln!(f, "block :{:x}", block.start)?;
+            for (offset, instruction) in &block.instructions {
+                writeln!(f, "  {:x}: {}", offset, instruction)?;
+            }
+            for edge in &block.edges {
+                match edge {
+                    Edge::Jump(target) => writeln!(f, "  -> :{:x}", target)?,
+                    Edge::Fallthrough(target) => writeln!(f, "  -> :{:x} (fallthrough)", target)?,
+                    Edge::Unresolved => writeln!(f, "  -> ? (unresolved)")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_concrete_jump_to_jumpdest() {
+        // push1 0x04; jump; stop; jumpdest; push1 0x01; add
+        let instructions = vec![
+            (0, Instruction::Push(1, U256::from(4u64))),
+            (2, Instruction::Jump),
+            (3, Instruction::Stop),
+            (4, Instruction::JumpDest(4)),
+            (5, Instruction::Push(1, U256::from(1u64))),
+            (7, Instruction::Add),
+        ];
+        let cfg = Cfg::build(instructions);
+
+        let blocks = cfg.blocks();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].start, 0);
+        assert_eq!(blocks[0].edges, vec![Edge::Jump(4)]);
+        assert_eq!(blocks[1].start, 3);
+        assert!(blocks[1].edges.is_empty());
+        assert_eq!(blocks[2].start, 4);
+    }
+
+    #[test]
+    fn jumpi_gets_fallthrough_and_taken_edges() {
+        // push1 0x04; jumpi; stop; jumpdest
+        let instructions = vec![
+            (0, Instruction::Push(1, U256::from(4u64))),
+            (2, Instruction::JumpI),
+            (3, Instruction::Stop),
+            (4, Instruction::JumpDest(4)),
+        ];
+        let cfg = Cfg::build(instructions);
+
+        let blocks = cfg.blocks();
+        assert_eq!(blocks[0].edges, vec![Edge::Jump(4), Edge::Fallthrough(3)]);
+    }
+
+    #[test]
+    fn dynamic_jump_target_is_unresolved() {
+        // calldataload; jump
+        let instructions = vec![(0, Instruction::CallDataLoad), (1, Instruction::Jump)];
+        let cfg = Cfg::build(instructions);
+
+        assert_eq!(cfg.blocks()[0].edges, vec![Edge::Unresolved]);
+    }
+
+    #[test]
+    fn fallthrough_into_jumpdest_without_explicit_jump() {
+        // push1 0x00; jumpdest; stop -- the push's block ends at the
+        // jumpdest, which starts a new block reached by fallthrough.
+        let instructions = vec![
+            (0, Instruction::Push(1, U256::from(0u64))),
+            (2, Instruction::JumpDest(2)),
+            (3, Instruction::Stop),
+        ];
+        let cfg = Cfg::build(instructions);
+
+        let blocks = cfg.blocks();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].edges, vec![Edge::Fallthrough(2)]);
+    }
+}
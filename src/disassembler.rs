@@ -33,8 +33,11 @@ where
     ///
     /// Note that the disassembler accepts very permissive hex encoding that
     /// ignores whitespace characters.
-    fn read<'a>(&mut self, buf: &'a mut [u8]) -> Result<(), (usize, io::Error)> {
-        assert!(buf.len() % 2 == 0, "reading off number of hex characters");
+    fn read(&mut self, buf: &mut [u8]) -> Result<(), (usize, io::Error)> {
+        assert!(
+            buf.len().is_multiple_of(2),
+            "reading off number of hex characters"
+        );
         self.offset += buf.len() / 2;
         for i in 0..buf.len() {
             loop {
@@ -72,6 +75,11 @@ where
         Ok(U256::from_str_radix(str::from_utf8(buf)?, 16)?)
     }
 
+    /// Returns the byte offset of the next instruction to be read.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
     /// Reads the next instruction from the input stream.
     pub fn next_instruction(&mut self) -> Result<Option<Instruction>> {
         use Instruction::*;
@@ -136,9 +144,13 @@ where
             0x41 => Coinbase,
             0x42 => Timestamp,
             0x43 => Number,
-            0x44 => Difficulty,
+            0x44 => PrevRandao,
             0x45 => GasLimit,
             0x46 => ChainId,
+            0x47 => SelfBalance,
+            0x48 => BaseFee,
+            0x49 => BlobHash,
+            0x4a => BlobBaseFee,
             0x50 => Pop,
             0x51 => MLoad,
             0x52 => MStore,
@@ -151,6 +163,10 @@ where
             0x59 => MSize,
             0x5a => Gas,
             0x5b => JumpDest(self.offset - 1),
+            0x5c => TLoad,
+            0x5d => TStore,
+            0x5e => MCopy,
+            0x5f => Push0,
             0x60..=0x7f => {
                 let size = op - 0x5f;
                 Push(size, self.next_word(size)?)
@@ -2,7 +2,90 @@ use std::fmt::{self, Display, Formatter};
 
 use ethnum::U256;
 
+/// Yellow Paper fee tier `Gzero`: instructions with no gas cost.
+const GZERO: u64 = 0;
+/// Yellow Paper fee tier `Gbase`: cheap environment and stack operations.
+const GBASE: u64 = 2;
+/// Yellow Paper fee tier `Gverylow`: most arithmetic, bitwise and memory-word
+/// operations, as well as `PUSH`, `DUP` and `SWAP`.
+const GVERYLOW: u64 = 3;
+/// Yellow Paper fee tier `Glow`.
+const GLOW: u64 = 5;
+/// Yellow Paper fee tier `Gmid`.
+const GMID: u64 = 8;
+/// Yellow Paper fee tier `Ghigh`.
+const GHIGH: u64 = 10;
+/// Fixed cost of a `JUMPDEST`.
+const GJUMPDEST: u64 = 1;
+/// Fixed cost of a `BLOCKHASH`.
+const GBLOCKHASH: u64 = 20;
+/// Base cost of an `EXP`, excluding the per-byte cost of the exponent.
+const GEXP: u64 = 10;
+/// Base cost of a `KECCAK256`, excluding the per-word cost of the input.
+const GSHA3: u64 = 30;
+/// Base cost of account access instructions (`BALANCE`, `EXTCODESIZE`,
+/// `EXTCODEHASH`), excluding cold/warm access surcharges.
+const GEXTCODE: u64 = 700;
+/// Base cost of a `CALL`/`CALLCODE`/`DELEGATECALL`/`STATICCALL`, excluding
+/// value-transfer, account-creation and cold/warm access surcharges.
+const GCALL: u64 = 700;
+/// Base cost of a `LOG`, excluding the per-topic and per-byte costs.
+const GLOG: u64 = 375;
+/// Per-topic cost of a `LOG`.
+const GLOGTOPIC: u64 = 375;
+/// Base cost of a `CREATE`/`CREATE2`, excluding the memory and hashing costs.
+const GCREATE: u64 = 32000;
+/// Base cost of a `SELFDESTRUCT`, excluding the new-account surcharge.
+const GSELFDESTRUCT: u64 = 5000;
+/// Cost of a warm-slot `TLOAD`/`TSTORE` (EIP-1153).
+const GWARMACCESS: u64 = 100;
+
+/// The static gas cost of an instruction.
+///
+/// Some instructions have a gas cost that can only be determined at runtime
+/// (for example it depends on the size of a memory expansion, or whether a
+/// storage slot or account is already warm). For these, only the fixed base
+/// cost is known statically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasCost {
+    /// The exact, statically known gas cost.
+    Fixed(u64),
+    /// A fixed base cost, plus additional gas that can only be determined at
+    /// runtime.
+    Dynamic(u64),
+}
+
+impl GasCost {
+    /// Returns the statically known portion of the gas cost.
+    pub fn base(self) -> u64 {
+        match self {
+            GasCost::Fixed(cost) | GasCost::Dynamic(cost) => cost,
+        }
+    }
+}
+
+impl Display for GasCost {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            GasCost::Fixed(cost) => write!(f, "{}", cost),
+            GasCost::Dynamic(cost) => write!(f, "{}+dynamic", cost),
+        }
+    }
+}
+
+/// Computes the quadratic memory-expansion gas cost `Cmem(a)` for active
+/// memory of `words` 32-byte words.
+///
+/// Not called internally: exposed so that callers who know the concrete
+/// offset of a memory-touching instruction (e.g. via constant propagation)
+/// can add this on top of [`Instruction::gas_cost`]'s base cost.
+#[allow(dead_code)]
+pub fn memory_expansion_cost(words: u64) -> u64 {
+    3 * words + words * words / 512
+}
+
 /// An EVM instruction.
+#[derive(Debug, Clone)]
 pub enum Instruction {
     /// Halts execution.
     Stop,
@@ -99,12 +182,22 @@ pub enum Instruction {
     Timestamp,
     /// Get the block's number.
     Number,
-    /// Get the block's difficulty.
-    Difficulty,
+    /// Get the block's difficulty (pre-Merge) or previous RANDAO value
+    /// (post-Merge).
+    PrevRandao,
     /// Get the block's gas limit.
     GasLimit,
     /// Returns the current chain’s EIP-155 unique identifier.
     ChainId,
+    /// Get balance of currently executing account.
+    SelfBalance,
+    /// Get the base fee of the current block.
+    BaseFee,
+    /// Get the versioned hash of the `index`-th blob associated with this
+    /// transaction.
+    BlobHash,
+    /// Get the current blob base fee of the current block.
+    BlobBaseFee,
     /// Remove word from stack.
     Pop,
     /// Load word from memory.
@@ -123,6 +216,12 @@ pub enum Instruction {
     JumpI,
     /// Get the value of the program counter prior to the increment.
     GetPc,
+    /// Load word from transient storage.
+    TLoad,
+    /// Save word to transient storage.
+    TStore,
+    /// Copy memory to memory.
+    MCopy,
     /// Get the size of active memory in bytes.
     MSize,
     /// Get the amount of available gas, including the corresponding reduction
@@ -130,6 +229,8 @@ pub enum Instruction {
     Gas,
     /// Mark a valid destination for jumps.
     JumpDest(usize),
+    /// Place value 0 on the stack.
+    Push0,
     /// Place value on the stack.
     Push(u8, U256),
     /// Duplicate n-th stack item.
@@ -165,6 +266,104 @@ pub enum Instruction {
     Unknown(u8),
 }
 
+impl Instruction {
+    /// Returns the number of stack items this instruction pops and pushes,
+    /// as `(pops, pushes)`.
+    pub fn stack_effect(&self) -> (u16, u16) {
+        use Instruction::*;
+        match self {
+            Stop => (0, 0),
+            Add | Mul | Sub | Div | Sdiv | Mod | Smod | Exp | SignExtend | Lt | Gt | Slt | Sgt
+            | Eq | And | Or | Xor | Byte | Shl | Shr | Sar | Keccak256 => (2, 1),
+            AddMod | MulMod => (3, 1),
+            IsZero | Not => (1, 1),
+            Address | Origin | Caller | CallValue | CallDataSize | CodeSize | GasPrice
+            | ReturnDataSize | Coinbase | Timestamp | Number | PrevRandao | GasLimit | ChainId
+            | SelfBalance | BaseFee | BlobBaseFee | GetPc | MSize | Gas | Push0 => (0, 1),
+            Balance | CallDataLoad | ExtCodeSize | ExtCodeHash | BlockHash | MLoad | SLoad
+            | TLoad | BlobHash => (1, 1),
+            CallDataCopy | CodeCopy | ReturnDataCopy | MStore | MStore8 | SStore | TStore => {
+                (2, 0)
+            }
+            ExtCodeCopy | MCopy => (3, 0),
+            Pop => (1, 0),
+            Jump => (1, 0),
+            JumpI => (2, 0),
+            JumpDest(_) => (0, 0),
+            Push(..) => (0, 1),
+            Dup(n) => (u16::from(*n), u16::from(*n) + 1),
+            Swap(n) => (u16::from(*n) + 1, u16::from(*n) + 1),
+            Log(topics) => (u16::from(*topics) + 2, 0),
+            Create => (3, 1),
+            Call => (7, 1),
+            CallCode => (7, 1),
+            Return => (2, 0),
+            DelegateCall | StaticCall => (6, 1),
+            Create2 => (4, 1),
+            Revert => (2, 0),
+            Invalid => (0, 0),
+            SelfDestruct => (1, 0),
+            Unknown(_) => (0, 0),
+        }
+    }
+
+    /// Returns the static gas cost of this instruction.
+    ///
+    /// For instructions whose cost depends on runtime state (memory
+    /// expansion, storage slot transitions, cold/warm account access, ...)
+    /// this returns [`GasCost::Dynamic`] with the statically known base cost.
+    pub fn gas_cost(&self) -> GasCost {
+        use Instruction::*;
+        match self {
+            Stop | Return | Revert => GasCost::Fixed(GZERO),
+
+            Address | Origin | Caller | CallValue | CodeSize | GasPrice | Coinbase
+            | Timestamp | Number | PrevRandao | GasLimit | ChainId | BaseFee | BlobBaseFee
+            | Pop | GetPc | MSize | Gas | CallDataSize | ReturnDataSize | Push0 => {
+                GasCost::Fixed(GBASE)
+            }
+
+            Add | Sub | Not | Lt | Gt | Slt | Sgt | Eq | IsZero | And | Or | Xor | Byte | Shl
+            | Shr | Sar | CallDataLoad | MLoad | MStore | MStore8 | Push(..) | Dup(_)
+            | Swap(_) | BlobHash => GasCost::Fixed(GVERYLOW),
+
+            Mul | Div | Sdiv | Mod | Smod | SignExtend | SelfBalance => GasCost::Fixed(GLOW),
+
+            AddMod | MulMod | Jump => GasCost::Fixed(GMID),
+
+            JumpI => GasCost::Fixed(GHIGH),
+
+            JumpDest(_) => GasCost::Fixed(GJUMPDEST),
+            BlockHash => GasCost::Fixed(GBLOCKHASH),
+
+            TLoad | TStore => GasCost::Fixed(GWARMACCESS),
+
+            Exp => GasCost::Dynamic(GEXP),
+            Keccak256 => GasCost::Dynamic(GSHA3),
+
+            Balance | ExtCodeSize | ExtCodeHash => GasCost::Dynamic(GEXTCODE),
+            ExtCodeCopy => GasCost::Dynamic(GEXTCODE),
+            CallDataCopy | CodeCopy | ReturnDataCopy | MCopy => GasCost::Dynamic(GVERYLOW),
+
+            SLoad | SStore => GasCost::Dynamic(0),
+
+            Call | CallCode | DelegateCall | StaticCall => GasCost::Dynamic(GCALL),
+
+            Log(topics) => GasCost::Dynamic(GLOG + GLOGTOPIC * u64::from(*topics)),
+
+            Create | Create2 => GasCost::Dynamic(GCREATE),
+
+            SelfDestruct => GasCost::Dynamic(GSELFDESTRUCT),
+
+            // Consumes all remaining gas; not meaningfully expressible as a
+            // small fixed or base cost.
+            Invalid => GasCost::Dynamic(0),
+
+            Unknown(_) => GasCost::Fixed(GZERO),
+        }
+    }
+}
+
 impl Display for Instruction {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         use Instruction::*;
@@ -216,9 +415,13 @@ impl Display for Instruction {
             Coinbase => f.write_str("coinbase"),
             Timestamp => f.write_str("timestamp"),
             Number => f.write_str("number"),
-            Difficulty => f.write_str("difficulty"),
+            PrevRandao => f.write_str("prevrandao"),
             GasLimit => f.write_str("gaslimit"),
             ChainId => f.write_str("chainid"),
+            SelfBalance => f.write_str("selfbalance"),
+            BaseFee => f.write_str("basefee"),
+            BlobHash => f.write_str("blobhash"),
+            BlobBaseFee => f.write_str("blobbasefee"),
             Pop => f.write_str("pop"),
             MLoad => f.write_str("mload"),
             MStore => f.write_str("mstore"),
@@ -228,9 +431,13 @@ impl Display for Instruction {
             Jump => f.write_str("jump"),
             JumpI => f.write_str("jumpi"),
             GetPc => f.write_str("getpc"),
+            TLoad => f.write_str("tload"),
+            TStore => f.write_str("tstore"),
+            MCopy => f.write_str("mcopy"),
             MSize => f.write_str("msize"),
             Gas => f.write_str("gas"),
             JumpDest(offset) => write!(f, "jumpdest :{:x}", offset),
+            Push0 => f.write_str("push0"),
             Push(size, value) => write!(f, "push{} {:0w$x}", size, value, w = (size * 2) as usize),
             Dup(slot) => write!(f, "dup{}", slot),
             Swap(slot) => write!(f, "swap{}", slot),
@@ -249,3 +456,79 @@ impl Display for Instruction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembler::Disassembler;
+
+    /// Decodes a single instruction from a hex-encoded opcode byte.
+    fn decode_one(hex: &str) -> Instruction {
+        let mut disassembler = Disassembler::new(hex.as_bytes());
+        disassembler.next_instruction().unwrap().unwrap()
+    }
+
+    #[test]
+    fn gas_cost_tiers_match_yellow_paper() {
+        assert_eq!(Instruction::Stop.gas_cost(), GasCost::Fixed(GZERO));
+        assert_eq!(Instruction::Pop.gas_cost(), GasCost::Fixed(GBASE));
+        assert_eq!(Instruction::Add.gas_cost(), GasCost::Fixed(GVERYLOW));
+        assert_eq!(Instruction::Mul.gas_cost(), GasCost::Fixed(GLOW));
+        assert_eq!(Instruction::AddMod.gas_cost(), GasCost::Fixed(GMID));
+        assert_eq!(Instruction::JumpI.gas_cost(), GasCost::Fixed(GHIGH));
+        assert_eq!(
+            Instruction::JumpDest(0).gas_cost(),
+            GasCost::Fixed(GJUMPDEST)
+        );
+        assert_eq!(Instruction::BlockHash.gas_cost(), GasCost::Fixed(GBLOCKHASH));
+        assert_eq!(Instruction::TLoad.gas_cost(), GasCost::Fixed(GWARMACCESS));
+        assert_eq!(Instruction::TStore.gas_cost(), GasCost::Fixed(GWARMACCESS));
+    }
+
+    #[test]
+    fn gas_cost_dynamic_costs_report_known_base() {
+        assert_eq!(Instruction::Exp.gas_cost(), GasCost::Dynamic(GEXP));
+        assert_eq!(Instruction::Keccak256.gas_cost(), GasCost::Dynamic(GSHA3));
+        assert_eq!(Instruction::Balance.gas_cost(), GasCost::Dynamic(GEXTCODE));
+        assert_eq!(Instruction::Call.gas_cost(), GasCost::Dynamic(GCALL));
+        assert_eq!(
+            Instruction::Log(2).gas_cost(),
+            GasCost::Dynamic(GLOG + GLOGTOPIC * 2)
+        );
+        assert_eq!(Instruction::Create.gas_cost(), GasCost::Dynamic(GCREATE));
+        assert_eq!(
+            Instruction::SelfDestruct.gas_cost(),
+            GasCost::Dynamic(GSELFDESTRUCT)
+        );
+    }
+
+    #[test]
+    fn gas_cost_display_appends_dynamic_marker() {
+        assert_eq!(GasCost::Fixed(3).to_string(), "3");
+        assert_eq!(GasCost::Dynamic(700).to_string(), "700+dynamic");
+    }
+
+    #[test]
+    fn decodes_shanghai_and_cancun_opcodes() {
+        assert!(matches!(decode_one("5f"), Instruction::Push0));
+        assert!(matches!(decode_one("47"), Instruction::SelfBalance));
+        assert!(matches!(decode_one("48"), Instruction::BaseFee));
+        assert!(matches!(decode_one("49"), Instruction::BlobHash));
+        assert!(matches!(decode_one("4a"), Instruction::BlobBaseFee));
+        assert!(matches!(decode_one("5c"), Instruction::TLoad));
+        assert!(matches!(decode_one("5d"), Instruction::TStore));
+        assert!(matches!(decode_one("5e"), Instruction::MCopy));
+    }
+
+    #[test]
+    fn new_opcode_mnemonics_match_display() {
+        assert_eq!(Instruction::Push0.to_string(), "push0");
+        assert_eq!(Instruction::SelfBalance.to_string(), "selfbalance");
+        assert_eq!(Instruction::BaseFee.to_string(), "basefee");
+        assert_eq!(Instruction::BlobHash.to_string(), "blobhash");
+        assert_eq!(Instruction::BlobBaseFee.to_string(), "blobbasefee");
+        assert_eq!(Instruction::TLoad.to_string(), "tload");
+        assert_eq!(Instruction::TStore.to_string(), "tstore");
+        assert_eq!(Instruction::MCopy.to_string(), "mcopy");
+    }
+}
@@ -1,12 +1,22 @@
+mod analysis;
+mod annotate;
+mod cfg;
 mod disassembler;
 mod instruction;
 
-use crate::disassembler::Disassembler;
+use crate::{
+    analysis::StackAnalysis,
+    cfg::Cfg,
+    disassembler::Disassembler,
+    instruction::{GasCost, Instruction},
+};
 use anyhow::Result;
 use std::{
+    collections::HashMap,
     fs::File,
     io::{self, Read},
     path::PathBuf,
+    str::FromStr,
 };
 use structopt::StructOpt;
 
@@ -15,22 +25,152 @@ struct Options {
     /// The file containing hex-encoded EVM bytecode to disassemble.
     #[structopt(name = "FILE")]
     file: Option<PathBuf>,
+
+    /// Annotate each instruction with its static gas cost and print a
+    /// running total, marked "+dynamic" once it is no longer statically
+    /// known.
+    #[structopt(long)]
+    gas: bool,
+
+    /// Print a control-flow graph instead of a flat disassembly, as either
+    /// annotated assembly ("asm") or Graphviz DOT ("dot"). The format has no
+    /// default and must be given.
+    #[structopt(long, value_name = "FORMAT")]
+    cfg: Option<CfgFormat>,
+
+    /// Annotate each instruction with its symbolic stack height and flag
+    /// static stack underflows and inconsistent block heights.
+    #[structopt(long)]
+    stack: bool,
+
+    /// Annotate calls to known precompiles and Solidity-style 4-byte
+    /// selector dispatch with inline comments.
+    #[structopt(long)]
+    annotate: bool,
+}
+
+/// The output format used for the `--cfg` flag.
+#[derive(Debug)]
+enum CfgFormat {
+    /// Annotated assembly: each block labeled with its successors listed.
+    Asm,
+    /// Graphviz DOT.
+    Dot,
+}
+
+impl FromStr for CfgFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "asm" => Ok(Self::Asm),
+            "dot" => Ok(Self::Dot),
+            _ => anyhow::bail!("unknown CFG format '{}', expected 'asm' or 'dot'", s),
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let options = Options::from_args();
 
     let file = options.file.unwrap_or_else(|| PathBuf::from("-"));
-    match file.to_str() {
-        Some("-") => disassemble(io::stdin().lock()),
-        _ => disassemble(File::open(file)?),
+    let input = match file.to_str() {
+        Some("-") => Box::new(io::stdin().lock()) as Box<dyn Read>,
+        _ => Box::new(File::open(file)?),
+    };
+
+    match options.cfg {
+        Some(format) => print_cfg(input, format),
+        None if options.stack => print_stack_analysis(input),
+        None if options.annotate => print_annotated(input),
+        None => disassemble(input, options.gas),
     }
 }
 
-fn disassemble(input: impl Read) -> Result<()> {
+/// Reads the full instruction stream from `input`, keyed by byte offset.
+fn read_instructions(input: impl Read) -> Result<Vec<(usize, Instruction)>> {
     let mut disassembler = Disassembler::new(input);
+    let mut instructions = Vec::new();
+    loop {
+        let offset = disassembler.offset();
+        match disassembler.next_instruction()? {
+            Some(instruction) => instructions.push((offset, instruction)),
+            None => break,
+        }
+    }
+    Ok(instructions)
+}
+
+fn disassemble(input: impl Read, gas: bool) -> Result<()> {
+    let mut disassembler = Disassembler::new(input);
+    let mut total_gas = 0u64;
+    let mut total_dynamic = false;
     while let Some(instruction) = disassembler.next_instruction()? {
-        println!("{}", instruction);
+        if gas {
+            let cost = instruction.gas_cost();
+            total_gas += cost.base();
+            total_dynamic |= matches!(cost, GasCost::Dynamic(_));
+            let total = if total_dynamic {
+                GasCost::Dynamic(total_gas)
+            } else {
+                GasCost::Fixed(total_gas)
+            };
+            println!(
+                "{:<30} ; gas={} total={}",
+                instruction.to_string(),
+                cost,
+                total
+            );
+        } else {
+            println!("{}", instruction);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_cfg(input: impl Read, format: CfgFormat) -> Result<()> {
+    let cfg = Cfg::build(read_instructions(input)?);
+    match format {
+        CfgFormat::Asm => print!("{}", cfg),
+        CfgFormat::Dot => print!("{}", cfg.to_dot()),
+    }
+
+    Ok(())
+}
+
+fn print_stack_analysis(input: impl Read) -> Result<()> {
+    let instructions = read_instructions(input)?;
+    let cfg = Cfg::build(instructions.clone());
+    let StackAnalysis {
+        heights,
+        diagnostics,
+    } = analysis::analyze(&instructions, Some(&cfg));
+
+    let by_offset = instructions.into_iter().collect::<HashMap<_, _>>();
+    for height in &heights {
+        let instruction = &by_offset[&height.offset];
+        let marker = if height.underflow { " ! underflow" } else { "" };
+        println!("[{:>3}] {}{}", height.height, instruction, marker);
+    }
+
+    for diagnostic in &diagnostics {
+        println!("; {}", diagnostic);
+    }
+
+    Ok(())
+}
+
+fn print_annotated(input: impl Read) -> Result<()> {
+    let instructions = read_instructions(input)?;
+    let cfg = Cfg::build(instructions.clone());
+    let comments = annotate::annotate(&cfg);
+
+    for (offset, instruction) in &instructions {
+        match comments.get(offset) {
+            Some(comment) => println!("{:<30} # {}", instruction.to_string(), comment),
+            None => println!("{}", instruction),
+        }
     }
 
     Ok(())